@@ -1,17 +1,159 @@
 mod token;
 
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 pub use token::{Token, TokenType};
 
+/// Outcome of a resumable lex attempt, see [`Lexer::next_token_resumable`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum TokenResult {
+    Token(Token),
+    /// A token was started but the buffer ran out mid-token; `feed` more
+    /// and retry.
+    Incomplete,
+    /// The buffer ran out exactly between tokens, with nothing partially
+    /// read. Unlike `Incomplete`, this is a clean place to stop: a caller
+    /// that knows no more bytes are coming (e.g. the socket closed) can
+    /// treat this as the legitimate end of the stream, while still being
+    /// free to `feed` more and retry if it knows otherwise.
+    Eof,
+}
+
 //TODO: use bytes to improve input data and support binary
 pub struct Lexer {
-    input: Bytes,
+    input: BytesMut,
     idx: usize,
+    last_len: Option<usize>,
 }
 
 impl Lexer {
     pub fn new(input: Bytes) -> Self {
-        Self { input, idx: 0 }
+        let mut buf = BytesMut::with_capacity(input.len());
+        buf.extend_from_slice(&input);
+        Self {
+            input: buf,
+            idx: 0,
+            last_len: None,
+        }
+    }
+
+    /// Appends more bytes to the input, e.g. once a caller draining a socket
+    /// has more data available after a [`TokenResult::Incomplete`] or
+    /// [`TokenResult::Eof`]. Grows the buffer in place rather than
+    /// recopying what's already there, so repeated small feeds (e.g. a
+    /// socket drained a byte at a time) stay amortized O(1) each instead of
+    /// O(n) per call.
+    pub fn feed(&mut self, more: Bytes) {
+        self.input.extend_from_slice(&more);
+    }
+
+    /// Resumable variant of [`Lexer::next_token`] for streaming callers that
+    /// may only have a partial frame buffered so far.
+    ///
+    /// On [`TokenResult::Incomplete`] `idx` is rewound to the start of the
+    /// token that could not be completed, so re-calling this after a
+    /// [`Lexer::feed`] reproduces exactly the tokens a single-shot parse of
+    /// the concatenated buffer would have produced.
+    pub fn next_token_resumable(&mut self) -> TokenResult {
+        let committed = self.idx;
+        self.skip_until(|c| c.is_ascii_whitespace());
+        let start = self.idx;
+        let token = match self.current_char() {
+            b'+' if self.next_char() == &b'l' => {
+                self.consume();
+                self.consume();
+                let digit_start = self.idx;
+                self.skip_until(|c| c.is_ascii_digit());
+                let digit_end = self.idx;
+                if digit_end == self.input.len() {
+                    None
+                } else {
+                    let number_slice = Bytes::copy_from_slice(&self.input[digit_start..digit_end]);
+                    let number_string = String::from_utf8_lossy(&number_slice);
+                    match number_string.parse::<usize>() {
+                        Ok(number) => {
+                            self.last_len = Some(number);
+                            Some(Token::new(TokenType::Len(number), None, start..digit_end))
+                        }
+                        Err(_) => Some(Token::new(
+                            TokenType::Illegal,
+                            Some(number_slice),
+                            start..digit_end,
+                        )),
+                    }
+                }
+            }
+            (b'a'..=b'z') | (b'A'..=b'Z') | b'_' => {
+                self.skip_until(|c| c.is_ascii_alphanumeric() || c == &b'_' || c == &b'.');
+                let end = self.idx;
+                if end == self.input.len() {
+                    None
+                } else {
+                    self.consume();
+                    let slice = &self.input[start..end];
+                    let token_type = TokenType::from(slice);
+                    self.last_len = None;
+                    Some(Token::new(
+                        token_type,
+                        Some(Bytes::copy_from_slice(slice)),
+                        start..end,
+                    ))
+                }
+            }
+            b';' => {
+                self.consume();
+                self.last_len = None;
+                Some(Token::new(TokenType::Semicolon, None, start..self.idx))
+            }
+            b'#' => {
+                // peek rather than take: on `Incomplete` we must still
+                // remember the declared length for the retry after `feed`.
+                // '#' itself is only consumed once we know a full body is
+                // buffered, so the bounds check accounts for it with `+ 1`.
+                match self.last_len {
+                    Some(len) => {
+                        if self.input.len() - (self.idx + 1) < len {
+                            None
+                        } else {
+                            self.consume();
+                            let bytes =
+                                Bytes::copy_from_slice(&self.input[self.idx..self.idx + len]);
+                            self.idx += len;
+                            self.last_len = None;
+                            Some(Token::new(TokenType::Binary, Some(bytes), start..self.idx))
+                        }
+                    }
+                    None => {
+                        self.consume();
+                        let bytes = Bytes::copy_from_slice(&self.input[self.idx..]);
+                        self.idx = self.input.len();
+                        Some(Token::new(TokenType::Binary, Some(bytes), start..self.idx))
+                    }
+                }
+            }
+            0 => return TokenResult::Eof,
+            _ => {
+                self.skip_until(|c| !c.is_ascii_whitespace() && c != &0u8);
+                let end = self.idx;
+                if end == self.input.len() {
+                    None
+                } else {
+                    self.last_len = None;
+                    Some(Token::new(
+                        TokenType::Illegal,
+                        Some(Bytes::copy_from_slice(&self.input[start..end])),
+                        start..end,
+                    ))
+                }
+            }
+        };
+
+        match token {
+            Some(token) => TokenResult::Token(token),
+            None => {
+                self.idx = committed;
+                TokenResult::Incomplete
+            }
+        }
     }
 
     pub fn next_token(&mut self) -> Token {
@@ -21,17 +163,21 @@ impl Lexer {
             b'+' if self.next_char() == &b'l' => {
                 self.consume();
                 self.consume();
-                let start = self.idx;
+                let digit_start = self.idx;
                 self.skip_until(|c| c.is_ascii_digit());
                 let end = self.idx;
-                let number_slice = Bytes::copy_from_slice(&self.input[start..end]);
+                let number_slice = Bytes::copy_from_slice(&self.input[digit_start..end]);
 
                 let number_string = String::from_utf8_lossy(&number_slice);
                 let number: usize = match number_string.parse() {
                     Ok(number) => number,
-                    Err(_) => return Token::new(TokenType::Illegal, Some(number_slice)),
+                    Err(_) => {
+                        self.last_len = None;
+                        return Token::new(TokenType::Illegal, Some(number_slice), start..end);
+                    }
                 };
-                Token::new(TokenType::Len(number), None)
+                self.last_len = Some(number);
+                Token::new(TokenType::Len(number), None, start..end)
             }
             (b'a'..=b'z') | (b'A'..=b'Z') | b'_' => {
                 self.skip_until(|c| c.is_ascii_alphanumeric() || c == &b'_' || c == &b'.');
@@ -40,29 +186,44 @@ impl Lexer {
                 let slice = &self.input[start..end];
                 let token_type = TokenType::from(slice);
 
-                Token::new(token_type, Some(Bytes::copy_from_slice(slice)))
+                self.last_len = None;
+                Token::new(token_type, Some(Bytes::copy_from_slice(slice)), start..end)
             }
             b';' => {
                 self.consume();
-                Token::new(TokenType::Semicolon, None)
+                self.last_len = None;
+                Token::new(TokenType::Semicolon, None, start..self.idx)
             }
             b'#' => {
                 self.consume();
-                let token = Token::new(
-                    TokenType::Binary,
-                    Some(Bytes::copy_from_slice(&self.input[self.idx..])),
-                );
-                self.idx = self.input.len();
-                token
+                match self.last_len.take() {
+                    Some(len) if self.input.len() - self.idx >= len => {
+                        let bytes = Bytes::copy_from_slice(&self.input[self.idx..self.idx + len]);
+                        self.idx += len;
+                        Token::new(TokenType::Binary, Some(bytes), start..self.idx)
+                    }
+                    Some(_) => {
+                        let bytes = Bytes::copy_from_slice(&self.input[self.idx..]);
+                        self.idx = self.input.len();
+                        Token::new(TokenType::Illegal, Some(bytes), start..self.idx)
+                    }
+                    None => {
+                        let bytes = Bytes::copy_from_slice(&self.input[self.idx..]);
+                        self.idx = self.input.len();
+                        Token::new(TokenType::Binary, Some(bytes), start..self.idx)
+                    }
+                }
             }
-            0 => Token::new(TokenType::Eof, None),
+            0 => Token::new(TokenType::Eof, None, start..start),
             _ => {
                 let start = self.idx;
                 self.skip_until(|c| !c.is_ascii_whitespace() && c != &0u8);
                 let end = self.idx;
+                self.last_len = None;
                 Token::new(
                     TokenType::Illegal,
                     Some(Bytes::copy_from_slice(&self.input[start..end])),
+                    start..end,
                 )
             }
         }
@@ -172,11 +333,37 @@ mod tests {
     fn given_sequence_value_should_be_ok() {
         let input = Bytes::from_static(b"subscribe foo with group bar");
         let expecteds = [
-            Token::new(TokenType::Subscribe, Some(Bytes::from_static(b"subscribe"))),
-            Token::new(TokenType::Name, Some(Bytes::from_static(b"foo"))),
-            Token::new(TokenType::With, Some(Bytes::from_static(b"with"))),
-            Token::new(TokenType::Group, Some(Bytes::from_static(b"group"))),
-            Token::new(TokenType::Name, Some(Bytes::from_static(b"bar"))),
+            Token::new(
+                TokenType::Subscribe,
+                Some(Bytes::from_static(b"subscribe")),
+                0..0,
+            ),
+            Token::new(TokenType::Name, Some(Bytes::from_static(b"foo")), 0..0),
+            Token::new(TokenType::With, Some(Bytes::from_static(b"with")), 0..0),
+            Token::new(TokenType::Group, Some(Bytes::from_static(b"group")), 0..0),
+            Token::new(TokenType::Name, Some(Bytes::from_static(b"bar")), 0..0),
+        ];
+        let mut lexer = Lexer::new(input);
+        for expected in expecteds {
+            let tok = lexer.next_token();
+            assert_eq!(expected, tok, "expected {expected:?} but got {tok:?}");
+        }
+    }
+
+    #[test]
+    fn given_a_len_the_binary_body_should_stop_after_exactly_len_bytes() {
+        let input = Bytes::from_static(b"+l3 #foo message +l3 #bar;");
+        let expecteds = [
+            Token::new(TokenType::Len(3), None, 0..0),
+            Token::new(TokenType::Binary, Some(Bytes::from_static(b"foo")), 0..0),
+            Token::new(
+                TokenType::Message,
+                Some(Bytes::from_static(b"message")),
+                0..0,
+            ),
+            Token::new(TokenType::Len(3), None, 0..0),
+            Token::new(TokenType::Binary, Some(Bytes::from_static(b"bar")), 0..0),
+            Token::new(TokenType::Semicolon, None, 0..0),
         ];
         let mut lexer = Lexer::new(input);
         for expected in expecteds {
@@ -184,4 +371,122 @@ mod tests {
             assert_eq!(expected, tok, "expected {expected:?} but got {tok:?}");
         }
     }
+
+    #[test]
+    fn given_fewer_bytes_than_the_declared_len_should_be_illegal() {
+        let mut lexer = Lexer::new(Bytes::from_static(b"+l10 #foo"));
+        assert_eq!(
+            Token::new(TokenType::Len(10), None, 0..0),
+            lexer.next_token()
+        );
+        assert_eq!(
+            Token::new(TokenType::Illegal, Some(Bytes::from_static(b"foo")), 0..0),
+            lexer.next_token()
+        );
+    }
+
+    #[test]
+    fn given_a_half_read_keyword_should_be_incomplete_until_fed() {
+        let mut lexer = Lexer::new(Bytes::from_static(b"mess"));
+        assert_eq!(TokenResult::Incomplete, lexer.next_token_resumable());
+        lexer.feed(Bytes::from_static(b"age +l3 #foo;"));
+        assert_eq!(
+            TokenResult::Token(Token::new(
+                TokenType::Message,
+                Some(Bytes::from_static(b"message")),
+                0..0
+            )),
+            lexer.next_token_resumable()
+        );
+        assert_eq!(
+            TokenResult::Token(Token::new(TokenType::Len(3), None, 0..0)),
+            lexer.next_token_resumable()
+        );
+        assert_eq!(
+            TokenResult::Token(Token::new(
+                TokenType::Binary,
+                Some(Bytes::from_static(b"foo")),
+                0..0
+            )),
+            lexer.next_token_resumable()
+        );
+        assert_eq!(
+            TokenResult::Token(Token::new(TokenType::Semicolon, None, 0..0)),
+            lexer.next_token_resumable()
+        );
+    }
+
+    #[test]
+    fn given_a_binary_body_shorter_than_its_declared_len_should_be_incomplete() {
+        let mut lexer = Lexer::new(Bytes::from_static(b"+l5 #fo"));
+        assert_eq!(
+            TokenResult::Token(Token::new(TokenType::Len(5), None, 0..0)),
+            lexer.next_token_resumable()
+        );
+        assert_eq!(TokenResult::Incomplete, lexer.next_token_resumable());
+        lexer.feed(Bytes::from_static(b"obar"));
+        assert_eq!(
+            TokenResult::Token(Token::new(
+                TokenType::Binary,
+                Some(Bytes::from_static(b"fooba")),
+                0..0
+            )),
+            lexer.next_token_resumable()
+        );
+    }
+
+    #[test]
+    fn feeding_in_chunks_reproduces_a_single_shot_parse() {
+        let full = Bytes::from_static(b"publisher foo; message +l3 #bar;");
+        let mut one_shot = Lexer::new(full.clone());
+        let mut expecteds = vec![];
+        loop {
+            let tok = one_shot.next_token();
+            let is_eof = tok.token_type() == TokenType::Eof;
+            expecteds.push(tok);
+            if is_eof {
+                break;
+            }
+        }
+
+        let mut resumable = Lexer::new(Bytes::new());
+        let mut chunks = full.chunks(1);
+        let mut produced = vec![];
+        loop {
+            match resumable.next_token_resumable() {
+                TokenResult::Token(tok) => produced.push(tok),
+                // The buffer can run dry exactly between tokens (Eof) or
+                // mid-token (Incomplete) well before the real input is
+                // exhausted; as long as this test's byte-by-byte feeder
+                // still has chunks left, either just means "feed more".
+                TokenResult::Eof if chunks.len() > 0 => {
+                    resumable.feed(Bytes::copy_from_slice(chunks.next().unwrap()))
+                }
+                TokenResult::Eof => {
+                    produced.push(Token::new(TokenType::Eof, None, 0..0));
+                    break;
+                }
+                TokenResult::Incomplete => match chunks.next() {
+                    Some(chunk) => resumable.feed(Bytes::copy_from_slice(chunk)),
+                    None => panic!("ran out of input with a token still in progress"),
+                },
+            }
+        }
+
+        assert_eq!(expecteds, produced);
+    }
+
+    #[test]
+    fn given_a_complete_command_next_token_resumable_should_report_eof_not_incomplete() {
+        let mut lexer = Lexer::new(Bytes::from_static(b"publisher foo;"));
+        assert!(matches!(
+            lexer.next_token_resumable(),
+            TokenResult::Token(_)
+        ));
+        assert!(matches!(
+            lexer.next_token_resumable(),
+            TokenResult::Token(_)
+        ));
+        assert_eq!(TokenResult::Eof, lexer.next_token_resumable());
+    }
 }
@@ -1,4 +1,5 @@
 use std::fmt::Display;
+use std::ops::Range;
 
 use bytes::Bytes;
 
@@ -61,15 +62,20 @@ impl Display for TokenType {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Eq)]
 pub struct Token {
     token_type: TokenType,
     value: Option<Bytes>,
+    span: Range<usize>,
 }
 
 impl Token {
-    pub fn new(token_type: TokenType, value: Option<Bytes>) -> Self {
-        Self { token_type, value }
+    pub fn new(token_type: TokenType, value: Option<Bytes>, span: Range<usize>) -> Self {
+        Self {
+            token_type,
+            value,
+            span,
+        }
     }
 
     pub fn token_type(&self) -> TokenType {
@@ -79,6 +85,21 @@ impl Token {
     pub fn value(&self) -> Option<Bytes> {
         self.value.clone()
     }
+
+    /// Byte offsets of this token in the input it was lexed from.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+}
+
+// The span is positional metadata, not part of a token's identity, so two
+// tokens of the same type/value are equal regardless of where they came
+// from (this is what lets tests build expected tokens without tracking
+// exact offsets).
+impl PartialEq for Token {
+    fn eq(&self, other: &Self) -> bool {
+        self.token_type == other.token_type && self.value == other.value
+    }
 }
 
 impl Display for Token {
@@ -1,7 +1,7 @@
 use crate::lexer::{Lexer, Token, TokenType};
 
 mod parse_error;
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 pub use parse_error::ParseError;
 
 use self::parse_error::ParseResult;
@@ -27,6 +27,50 @@ pub enum Command {
     },
 }
 
+impl Command {
+    /// Renders the command back into the exact wire text [`parse`] accepts,
+    /// the inverse of parsing. `Ok` and `Error` omit the trailing `;`: the
+    /// grammar only allows `Ok` at end of input, and `Error`'s binary body
+    /// reads to the end of the buffer, so a trailing `;` would be swallowed
+    /// into the message instead of acting as a separator.
+    pub fn encode(&self) -> Bytes {
+        let mut buf = BytesMut::new();
+        match self {
+            Command::Message { message, len } => {
+                buf.extend_from_slice(format!("message +l{len} #").as_bytes());
+                buf.extend_from_slice(message);
+                buf.extend_from_slice(b";");
+            }
+            Command::Error { message } => {
+                buf.extend_from_slice(b"error");
+                if let Some(message) = message {
+                    buf.extend_from_slice(b" #");
+                    buf.extend_from_slice(message);
+                }
+            }
+            Command::Publisher { queue_name } => {
+                buf.extend_from_slice(b"publisher ");
+                buf.extend_from_slice(queue_name);
+                buf.extend_from_slice(b";");
+            }
+            Command::Subscriber {
+                queue_name,
+                group_name,
+            } => {
+                buf.extend_from_slice(b"subscribe ");
+                buf.extend_from_slice(queue_name);
+                buf.extend_from_slice(b" with group ");
+                buf.extend_from_slice(group_name);
+                buf.extend_from_slice(b";");
+            }
+            Command::Ok { len } => {
+                buf.extend_from_slice(format!("ok +l{len}").as_bytes());
+            }
+        }
+        buf.freeze()
+    }
+}
+
 struct Parser {
     lexer: Lexer,
     current_tok: Token,
@@ -52,41 +96,63 @@ impl Parser {
                     break;
                 }
             }
-            let current_token = self.current_tok.token_type();
-            match current_token {
-                TokenType::Message => {
-                    let command = self.parse_message()?;
-                    commands.push(command);
-                },
-                TokenType::Publisher => {
-                    let command = self.parse_publisher()?;
-                    commands.push(command);
-                },
-                TokenType::Subscribe => {
-                    let command = self.parse_subscriber()?;
-                    commands.push(command);
-                },
-                TokenType::Ok => {
-                    let command = self.parse_ok()?;
-                    commands.push(command);
-                },
-                TokenType::Len(len) => {
-                    let command = self.parse_message_with_len(len)?;
-                    commands.push(command);
-                },
-                TokenType::Error => {
-                    let command = self.parse_error_message()?;
-                    commands.push(command);
+            let command = self.parse_one_command()?;
+            commands.push(command);
+        }
+        Ok(commands)
+    }
+
+    /// Like [`Parser::parse_commands`], but never bails on the first broken
+    /// command: when a command fails to parse, the error is collected and
+    /// the parser synchronizes to the next `;` (or `Eof`) before resuming,
+    /// so a batch of `;`-separated commands reports every diagnostic at
+    /// once instead of only the first.
+    pub fn parse_commands_recovering(&mut self) -> (Vec<Command>, Vec<ParseError>) {
+        let mut commands = vec![];
+        let mut errors = vec![];
+        while !self.current_token_is(TokenType::Eof) {
+            if self.current_token_is(TokenType::Semicolon) {
+                self.consume();
+                if self.current_token_is(TokenType::Eof) {
+                    break;
                 }
-                _ => {
-                    return Err(ParseError::new(format!(
-                        "miss expression, expression cannot start with '{}', only start with 'message', 'publisher', 'subscribe' or '+lx'",
-                        if let Some(ref value) = self.current_tok.value() { String::from_utf8_lossy(value) } else { String::from_utf8_lossy(b"any") }
-                    )))
+            }
+            match self.parse_one_command() {
+                Ok(command) => commands.push(command),
+                Err(err) => {
+                    errors.push(err);
+                    self.synchronize();
                 }
             }
         }
-        Ok(commands)
+        (commands, errors)
+    }
+
+    fn parse_one_command(&mut self) -> Result<Command, ParseError> {
+        match self.current_tok.token_type() {
+            TokenType::Message => self.parse_message(),
+            TokenType::Publisher => self.parse_publisher(),
+            TokenType::Subscribe => self.parse_subscriber(),
+            TokenType::Ok => self.parse_ok(),
+            TokenType::Len(len) => self.parse_message_with_len(len),
+            TokenType::Error => self.parse_error_message(),
+            _ => Err(ParseError::new(
+                format!(
+                    "miss expression, expression cannot start with '{}', only start with 'message', 'publisher', 'subscribe' or '+lx'",
+                    if let Some(ref value) = self.current_tok.value() { String::from_utf8_lossy(value) } else { String::from_utf8_lossy(b"any") }
+                ),
+                self.current_tok.span(),
+            )),
+        }
+    }
+
+    /// Consumes tokens until the next `;` or `Eof`, discarding whatever is
+    /// left of a broken command so parsing can resume at the next one.
+    fn synchronize(&mut self) {
+        while !self.current_token_is(TokenType::Semicolon) && !self.current_token_is(TokenType::Eof)
+        {
+            self.consume();
+        }
     }
 
     fn parse_message(&mut self) -> Result<Command, ParseError> {
@@ -160,10 +226,10 @@ impl Parser {
             return Ok(());
         }
 
-        Err(ParseError::new(format!(
-            "expected {} but got {}",
-            token_type, self.next_tok
-        )))
+        Err(ParseError::new(
+            format!("expected {} but got {}", token_type, self.next_tok),
+            self.next_tok.span(),
+        ))
     }
 
     fn expected_token_in(&mut self, tokens_types: &[TokenType]) -> Result<(), ParseError> {
@@ -174,10 +240,13 @@ impl Parser {
                 return Ok(());
             }
         }
-        Err(ParseError::new(format!(
-            "expected in {:?} but got {:?}",
-            tokens_types, next_tok_typen
-        )))
+        Err(ParseError::new(
+            format!(
+                "expected in {:?} but got {:?}",
+                tokens_types, next_tok_typen
+            ),
+            self.next_tok.span(),
+        ))
     }
 
     fn consume(&mut self) {
@@ -197,6 +266,17 @@ pub fn parse(input: Bytes) -> ParseResult {
     parser.parse_commands()
 }
 
+/// Like [`parse`], but recovers from a broken command instead of stopping
+/// at the first one, so every diagnostic in a `;`-separated batch is
+/// reported at once. Returns every command that parsed cleanly alongside
+/// every diagnostic, rather than discarding the successes when any command
+/// fails.
+#[inline(always)]
+pub fn parse_all(input: Bytes) -> (Vec<Command>, Vec<ParseError>) {
+    let mut parser = Parser::new(Lexer::new(input));
+    parser.parse_commands_recovering()
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -232,17 +312,17 @@ mod tests {
                 },
             ),
             (
-                "message +l19 #baz\";",
+                "message +l5 #baz\";",
                 Command::Message {
                     message: "baz\";".into(),
-                    len: 19usize,
+                    len: 5usize,
                 },
             ),
             (
-                "+l8 #foo",
+                "+l3 #foo",
                 Command::Message {
                     message: "foo".into(),
-                    len: 8usize,
+                    len: 3usize,
                 },
             ),
             (
@@ -283,18 +363,31 @@ mod tests {
                 }],
             ),
             (
-                "publisher foo; message +l19 #baz;",
+                "publisher foo; message +l4 #baz;",
                 vec![
                     Command::Publisher {
                         queue_name: "foo".into(),
                     },
                     Command::Message {
                         message: "baz;".into(),
-                        len: 19usize,
+                        len: 4usize,
                     },
                 ],
             ),
             ("ok +l400", vec![Command::Ok { len: 400 }]),
+            (
+                "message +l3 #foo message +l3 #bar;",
+                vec![
+                    Command::Message {
+                        message: "foo".into(),
+                        len: 3usize,
+                    },
+                    Command::Message {
+                        message: "bar".into(),
+                        len: 3usize,
+                    },
+                ],
+            ),
         ];
         for (input, expecteds) in cases {
             let mut parser = build_parser(input.into());
@@ -317,6 +410,121 @@ mod tests {
         }
     }
 
+    #[test]
+    fn given_a_batch_with_a_broken_command_should_recover_and_report_it() {
+        let input = "publisher ; message +l3 #foo ; subscribe bar with group baz;";
+        let mut parser = build_parser(input.into());
+        let (commands, errors) = parser.parse_commands_recovering();
+        assert_eq!(
+            vec![
+                Command::Message {
+                    message: "foo".into(),
+                    len: 3,
+                },
+                Command::Subscriber {
+                    queue_name: "bar".into(),
+                    group_name: "baz".into(),
+                },
+            ],
+            commands
+        );
+        assert_eq!(1, errors.len(), "expected a single error, got {errors:?}");
+    }
+
+    #[test]
+    fn given_a_clean_batch_parse_all_should_return_every_command() {
+        let input = "publisher foo; subscribe foo with group bar;";
+        let (commands, errors) = parse_all(input.into());
+        assert_eq!(2, commands.len());
+        assert!(errors.is_empty(), "expected no errors, got {errors:?}");
+    }
+
+    #[test]
+    fn given_a_broken_batch_parse_all_should_report_every_error() {
+        let input = "publisher ; subscribe ;";
+        let (commands, errors) = parse_all(input.into());
+        assert!(
+            commands.is_empty(),
+            "expected no commands, got {commands:?}"
+        );
+        assert_eq!(2, errors.len(), "expected two errors, got {errors:?}");
+    }
+
+    #[test]
+    fn given_a_partially_broken_batch_parse_all_should_keep_the_good_commands() {
+        let input = "publisher foo; subscribe ; message +l3 #bar;";
+        let (commands, errors) = parse_all(input.into());
+        assert_eq!(
+            vec![
+                Command::Publisher {
+                    queue_name: "foo".into(),
+                },
+                Command::Message {
+                    message: "bar".into(),
+                    len: 3,
+                },
+            ],
+            commands
+        );
+        assert_eq!(1, errors.len(), "expected a single error, got {errors:?}");
+    }
+
+    #[test]
+    fn given_a_missing_group_name_should_render_a_caret_diagnostic() {
+        let input: Bytes = "subscribe foo with group ;".into();
+        let mut parser = build_parser(input.clone());
+        let error = parser.parse_commands().expect_err("expected a parse error");
+        let rendered = error.render(&input);
+        assert!(
+            rendered.contains("line 1, column 26"),
+            "rendered diagnostic did not point at the right column: {rendered}"
+        );
+        assert!(
+            rendered.trim_end().ends_with('^'),
+            "rendered diagnostic should end with a caret: {rendered}"
+        );
+    }
+
+    #[test]
+    fn given_a_command_encode_then_parse_should_round_trip() {
+        let commands = [
+            Command::Message {
+                message: "baz".into(),
+                len: 3,
+            },
+            Command::Message {
+                message: "a;b#c def".into(),
+                len: 9,
+            },
+            Command::Error {
+                message: Some("boom".into()),
+            },
+            Command::Error { message: None },
+            Command::Publisher {
+                queue_name: "foo".into(),
+            },
+            Command::Subscriber {
+                queue_name: "foo".into(),
+                group_name: "bar".into(),
+            },
+            Command::Ok { len: 400 },
+        ];
+        for command in commands {
+            let encoded = command.encode();
+            match parse(encoded.clone()) {
+                Ok(parsed) => assert_eq!(
+                    vec![command.clone()],
+                    parsed,
+                    "encoding {command:?} as {encoded:?} did not round-trip",
+                ),
+                Err(e) => assert!(
+                    false,
+                    "failed to parse {command:?} back from its own encoding {encoded:?}: {e}",
+                ),
+            }
+        }
+    }
+
     fn build_parser(input: Bytes) -> Parser {
         let lexer = Lexer::new(input);
         Parser::new(lexer)
@@ -1,15 +1,57 @@
-use std::{error::Error, fmt::Display};
+use std::{error::Error, fmt::Display, ops::Range};
+
+use bytes::Bytes;
 
 use super::Command;
 
 #[derive(Debug)]
 pub struct ParseError {
     message: String,
+    span: Range<usize>,
 }
 
 impl ParseError {
-    pub fn new(message: String) -> Self {
-        Self { message }
+    pub fn new(message: String, span: Range<usize>) -> Self {
+        Self { message, span }
+    }
+
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// Renders this error as a human-readable diagnostic: the message,
+    /// followed by the offending source line with a `^^^` underline
+    /// beneath the span, e.g.:
+    ///
+    /// ```text
+    /// expected name but got [;: 'empty value']
+    ///   --> line 1, column 11
+    ///   | subscribe ;
+    ///   |           ^
+    /// ```
+    pub fn render(&self, input: &Bytes) -> String {
+        let offset = self.span.start.min(input.len());
+        let line_start = input[..offset]
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map(|idx| idx + 1)
+            .unwrap_or(0);
+        let line_end = input[offset..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map(|idx| offset + idx)
+            .unwrap_or(input.len());
+        let line = input[..offset].iter().filter(|&&b| b == b'\n').count() + 1;
+        let column = offset - line_start + 1;
+
+        let text = String::from_utf8_lossy(&input[line_start..line_end]);
+        let underline_len = self.span.end.saturating_sub(self.span.start).max(1);
+        let caret = " ".repeat(column - 1) + &"^".repeat(underline_len);
+
+        format!(
+            "{}\n  --> line {line}, column {column}\n  | {text}\n  | {caret}",
+            self.message
+        )
     }
 }
 